@@ -0,0 +1,500 @@
+//! Static analysis over decoded event scripts.
+
+use super::block::{Block, BlockId, CodeBlock, Ip};
+use super::command::Command;
+use super::expr::{Expr, SetExpr};
+use super::script::Script;
+use crate::stage::Settings;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt::Write as _;
+
+/// Builds a Graphviz `digraph` of the control-flow graph reachable from a set of root blocks.
+///
+/// Each `BlockId` becomes a node labeled with its disassembled commands. Fall-through edges and
+/// jump/branch edges (resolved through `Ip`) connect the nodes; conditional branches emit two
+/// edges labeled `"true"` and `"false"`.
+pub struct CfgBuilder<'a> {
+    script: &'a Script,
+}
+
+impl<'a> CfgBuilder<'a> {
+    pub fn new(script: &'a Script) -> Self {
+        Self { script }
+    }
+
+    /// Walks every block reachable from `roots` and renders them as a single DOT `digraph`.
+    pub fn build_dot(&self, roots: impl IntoIterator<Item = BlockId>) -> String {
+        let mut visited = HashSet::new();
+        let mut queue: VecDeque<BlockId> = roots.into_iter().collect();
+        let mut nodes = String::new();
+        let mut edges = String::new();
+
+        while let Some(id) = queue.pop_front() {
+            if !visited.insert(id) {
+                continue;
+            }
+            match self.script.block(id) {
+                Block::Code(code) => {
+                    let label = disassemble(code);
+                    writeln!(nodes, "  {} [shape=box, label={}];", node_name(id), dot_label(&label))
+                        .unwrap();
+                    for (branch, target) in code.successors() {
+                        if let Some(next) = target.resolved() {
+                            match branch {
+                                Some(taken) => writeln!(
+                                    edges,
+                                    "  {} -> {} [label=\"{}\"];",
+                                    node_name(id),
+                                    node_name(next),
+                                    if taken { "true" } else { "false" }
+                                )
+                                .unwrap(),
+                                None => {
+                                    writeln!(edges, "  {} -> {};", node_name(id), node_name(next)).unwrap()
+                                }
+                            }
+                            queue.push_back(next);
+                        }
+                    }
+                }
+                Block::Data(data) => {
+                    writeln!(
+                        nodes,
+                        "  {} [shape=ellipse, label={}];",
+                        node_name(id),
+                        dot_label(&format!("{:?}", data))
+                    )
+                    .unwrap();
+                }
+            }
+        }
+
+        format!("digraph cfg {{\n{}{}}}\n", nodes, edges)
+    }
+}
+
+/// Formats a code block's commands, one per line, for use as a node label.
+fn disassemble(code: &CodeBlock) -> String {
+    code.commands.iter().map(|cmd| cmd.to_string()).collect::<Vec<_>>().join("\n")
+}
+
+/// Escapes `s` as a quoted Graphviz label, left-justifying each line.
+fn dot_label(s: &str) -> String {
+    let mut escaped = String::from("\"");
+    for line in s.lines() {
+        for c in line.chars() {
+            match c {
+                '"' | '\\' => escaped.push('\\'),
+                _ => {}
+            }
+            escaped.push(c);
+        }
+        escaped.push_str("\\l");
+    }
+    escaped.push('"');
+    escaped
+}
+
+/// Builds a DOT-safe identifier for a block, independent of `BlockId`'s `Debug` format.
+fn node_name(id: BlockId) -> String {
+    let raw = format!("{:?}", id);
+    let mut name = String::from("block_");
+    name.extend(raw.chars().map(|c| if c.is_ascii_alphanumeric() { c } else { '_' }));
+    name
+}
+
+/// Identifies a storage location that liveness is tracked for: a script variable slot or a game
+/// flag. Flag ids are absolute; use [`VarId::classify`] to rebase a flag into whichever of
+/// `Settings`'s `item_flags_base`/`coin_flags_base`/`dust_flags_base` ranges it falls in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum VarId {
+    Variable(i16),
+    Flag(i16),
+}
+
+impl VarId {
+    /// Classifies a `Flag` against `settings`'s item/coin/dust flag-base ranges, rebasing it to an
+    /// index within whichever range it falls in. Returns `None` for `Variable`, which isn't
+    /// affected by these ranges.
+    pub fn classify(self, settings: &Settings) -> Option<FlagKind> {
+        match self {
+            VarId::Flag(flag) => Some(classify_flag(flag, settings)),
+            VarId::Variable(_) => None,
+        }
+    }
+}
+
+/// An absolute flag id rebased against `Settings`'s flag-base ranges: an index within the item,
+/// coin, or dust flag range, or an absolute id if it falls below every known range.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FlagKind {
+    Item(i16),
+    Coin(i16),
+    Dust(i16),
+    Other(i16),
+}
+
+/// Rebases an absolute flag id against `settings`'s `item_flags_base`/`coin_flags_base`/
+/// `dust_flags_base`, picking whichever range's base is the greatest one not exceeding `flag` (the
+/// ranges are contiguous and ordered by base, not by declaration order in `Settings`). Falls back
+/// to `FlagKind::Other` for a flag below every range's base.
+pub fn classify_flag(flag: i16, settings: &Settings) -> FlagKind {
+    let mut ranges: [(i16, fn(i16) -> FlagKind); 3] = [
+        (settings.item_flags_base, FlagKind::Item),
+        (settings.coin_flags_base, FlagKind::Coin),
+        (settings.dust_flags_base, FlagKind::Dust),
+    ];
+    ranges.sort_by_key(|&(base, _)| base);
+    ranges
+        .into_iter()
+        .rev()
+        .find(|&(base, _)| flag >= base)
+        .map(|(base, variant)| variant(flag - base))
+        .unwrap_or(FlagKind::Other(flag))
+}
+
+/// A set of `VarId`s, used to represent the live-in/live-out sets of a dataflow analysis. It is
+/// backed by a `HashSet`, not a bitset indexed by variable/flag id.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct LiveSet {
+    vars: HashSet<VarId>,
+}
+
+impl LiveSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn contains(&self, id: VarId) -> bool {
+        self.vars.contains(&id)
+    }
+
+    pub fn insert(&mut self, id: VarId) {
+        self.vars.insert(id);
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = VarId> + '_ {
+        self.vars.iter().copied()
+    }
+
+    /// Computes `self | other`, returning whether `self` changed.
+    fn union_with(&mut self, other: &Self) -> bool {
+        let before = self.vars.len();
+        self.vars.extend(&other.vars);
+        self.vars.len() != before
+    }
+
+    /// Computes `self - kill`, in place.
+    fn subtract(&mut self, kill: &Self) {
+        for id in &kill.vars {
+            self.vars.remove(id);
+        }
+    }
+}
+
+/// The `gen`/`kill` sets of a block: variables/flags read before any write (`gen`), and
+/// variables/flags written by the block (`kill`).
+#[derive(Debug, Clone, Default)]
+struct GenKill {
+    gen: LiveSet,
+    kill: LiveSet,
+}
+
+/// The result of a backward liveness analysis: the live-in and live-out sets of every block that
+/// was reachable from the analysis roots.
+#[derive(Debug, Clone, Default)]
+pub struct Liveness {
+    live_in: HashMap<BlockId, LiveSet>,
+    live_out: HashMap<BlockId, LiveSet>,
+}
+
+impl Liveness {
+    /// Variables/flags live at the entry of `block`.
+    pub fn live_in(&self, block: BlockId) -> Option<&LiveSet> {
+        self.live_in.get(&block)
+    }
+
+    /// Variables/flags live at the exit of `block`.
+    pub fn live_out(&self, block: BlockId) -> Option<&LiveSet> {
+        self.live_out.get(&block)
+    }
+
+    /// Flags/variables that `block` writes with a value that is never read: not by a later command
+    /// in the block before it is overwritten, and not after the block's exit. These are dead
+    /// assignments that could be removed without changing observable behavior.
+    pub fn dead_writes(&self, block: BlockId, code: &CodeBlock) -> Vec<VarId> {
+        let mut live = self.live_out.get(&block).cloned().unwrap_or_default();
+        let mut dead = Vec::new();
+        for cmd in code.commands.iter().rev() {
+            for write in cmd.writes() {
+                if !live.contains(write) && !dead.contains(&write) {
+                    dead.push(write);
+                }
+                live.vars.remove(&write);
+            }
+            for read in cmd.reads() {
+                live.insert(read);
+            }
+        }
+        dead
+    }
+
+    /// Runs the backward liveness analysis over every block reachable from `roots`.
+    pub fn compute(script: &Script, roots: impl IntoIterator<Item = BlockId>) -> Self {
+        let mut blocks = Vec::new();
+        let mut preds: HashMap<BlockId, Vec<BlockId>> = HashMap::new();
+        let mut visited = HashSet::new();
+        let mut queue: VecDeque<BlockId> = roots.into_iter().collect();
+        while let Some(id) = queue.pop_front() {
+            if !visited.insert(id) {
+                continue;
+            }
+            blocks.push(id);
+            if let Block::Code(code) = script.block(id) {
+                for (_, target) in code.successors() {
+                    if let Some(next) = target.resolved() {
+                        preds.entry(next).or_default().push(id);
+                        queue.push_back(next);
+                    }
+                }
+            }
+        }
+
+        let mut live_in: HashMap<BlockId, LiveSet> = HashMap::new();
+        let mut live_out: HashMap<BlockId, LiveSet> = HashMap::new();
+        let mut gen_kill_cache: HashMap<BlockId, GenKill> = HashMap::new();
+        for &id in &blocks {
+            if let Block::Code(code) = script.block(id) {
+                gen_kill_cache.insert(id, gen_kill(code));
+            }
+            live_in.insert(id, LiveSet::new());
+            live_out.insert(id, LiveSet::new());
+        }
+
+        // Classic backward worklist: OUT[b] = union of IN[succ], IN[b] = gen[b] | (OUT[b] - kill[b]).
+        let mut worklist: VecDeque<BlockId> = blocks.iter().copied().collect();
+        while let Some(id) = worklist.pop_front() {
+            let code = match script.block(id) {
+                Block::Code(code) => code,
+                Block::Data(_) => continue,
+            };
+
+            let mut out = LiveSet::new();
+            for (_, target) in code.successors() {
+                if let Some(next) = target.resolved() {
+                    if let Some(in_next) = live_in.get(&next) {
+                        out.union_with(in_next);
+                    }
+                }
+            }
+            live_out.insert(id, out.clone());
+
+            let gk = gen_kill_cache.entry(id).or_default();
+            let mut new_in = out;
+            new_in.subtract(&gk.kill);
+            new_in.union_with(&gk.gen);
+
+            let old_in = live_in.get(&id).cloned().unwrap_or_default();
+            if new_in != old_in {
+                live_in.insert(id, new_in);
+                for &pred in preds.get(&id).into_iter().flatten() {
+                    worklist.push_back(pred);
+                }
+            }
+        }
+
+        Self { live_in, live_out }
+    }
+}
+
+/// Computes the `gen`/`kill` sets for a single block, scanning its commands in order so that a
+/// read before any write to the same location counts toward `gen`.
+fn gen_kill(code: &CodeBlock) -> GenKill {
+    let mut result = GenKill::default();
+    for cmd in &code.commands {
+        for read in cmd.reads() {
+            if !result.kill.contains(read) {
+                result.gen.insert(read);
+            }
+        }
+        for write in cmd.writes() {
+            result.kill.insert(write);
+        }
+    }
+    result
+}
+
+/// Implemented by script expressions and commands to expose the variables/flags they read and
+/// write, which feeds the liveness analysis above.
+trait VarUse {
+    fn reads(&self) -> Vec<VarId>;
+    fn writes(&self) -> Vec<VarId>;
+}
+
+impl VarUse for Expr {
+    fn reads(&self) -> Vec<VarId> {
+        match self {
+            Expr::Variable(index) => vec![VarId::Variable(*index)],
+            Expr::Flag(index) => vec![VarId::Flag(*index)],
+            _ => Vec::new(),
+        }
+    }
+
+    fn writes(&self) -> Vec<VarId> {
+        Vec::new()
+    }
+}
+
+// Matched exhaustively (no wildcard arm) so a newly-added `Command` variant that reads or writes
+// a variable/flag fails to compile here instead of silently under-approximating liveness.
+impl VarUse for Command {
+    fn reads(&self) -> Vec<VarId> {
+        match self {
+            Command::Nop | Command::Return | Command::Goto(_) => Vec::new(),
+            Command::Set(set) => set.value.reads(),
+            Command::If(cond, _) => cond.reads(),
+            Command::SetFlag(_, value) => value.reads(),
+        }
+    }
+
+    fn writes(&self) -> Vec<VarId> {
+        match self {
+            Command::Nop | Command::Return | Command::Goto(_) | Command::If(..) => Vec::new(),
+            Command::Set(SetExpr { target, .. }) => target.reads(),
+            Command::SetFlag(flag, _) => vec![VarId::Flag(*flag)],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn code(commands: Vec<Command>, next: Option<BlockId>) -> Block {
+        Block::Code(CodeBlock { commands, next })
+    }
+
+    #[test]
+    fn dot_label_escapes_quotes_and_backslashes() {
+        let escaped = dot_label("say \"hi\" \\ bye\nsecond line");
+        // Every literal `"`/`\` in the input must itself be backslash-escaped, and the whole
+        // label must still be a single DOT-quoted string (opening/closing `"` only at the ends).
+        assert_eq!(escaped, "\"say \\\"hi\\\" \\\\ bye\\lsecond line\\l\"");
+    }
+
+    #[test]
+    fn build_dot_labels_conditional_branches_true_and_false() {
+        let mut script = Script::new();
+        let entry = script.alloc_block();
+        let taken = script.alloc_block();
+        let not_taken = script.alloc_block();
+        script.define_block(
+            entry,
+            code(vec![Command::If(Expr::Flag(1), Ip::Block(taken))], Some(not_taken)),
+        );
+        script.define_block(taken, code(vec![Command::Return], None));
+        script.define_block(not_taken, code(vec![Command::Return], None));
+
+        let dot = CfgBuilder::new(&script).build_dot([entry]);
+        assert!(dot.contains("[label=\"true\"];"), "missing true edge:\n{}", dot);
+        assert!(dot.contains("[label=\"false\"];"), "missing false edge:\n{}", dot);
+    }
+
+    fn settings(item_base: i16, coin_base: i16, dust_base: i16) -> Settings {
+        Settings {
+            unk_00: 0,
+            unk_04: 0,
+            unk_05: 0,
+            unk_06: 0,
+            unk_08: 0,
+            unk_09: 0,
+            item_flags_base: item_base,
+            coin_flags_base: coin_base,
+            dust_flags_base: dust_base,
+            unk_10: 0,
+            unk_12: 0,
+        }
+    }
+
+    #[test]
+    fn classify_flag_rebases_into_its_range() {
+        // item: [100, 200), coin: [200, 250), dust: [250, ...)
+        let s = settings(100, 200, 250);
+        assert_eq!(classify_flag(50, &s), FlagKind::Other(50));
+        assert_eq!(classify_flag(100, &s), FlagKind::Item(0));
+        assert_eq!(classify_flag(199, &s), FlagKind::Item(99));
+        assert_eq!(classify_flag(200, &s), FlagKind::Coin(0));
+        assert_eq!(classify_flag(260, &s), FlagKind::Dust(10));
+    }
+
+    #[test]
+    fn classify_flag_ignores_settings_field_declaration_order() {
+        // Bases declared out of numeric order still rebase correctly once sorted.
+        let s = settings(300, 50, 150);
+        assert_eq!(classify_flag(60, &s), FlagKind::Coin(10));
+        assert_eq!(classify_flag(160, &s), FlagKind::Dust(10));
+        assert_eq!(classify_flag(310, &s), FlagKind::Item(10));
+    }
+
+    #[test]
+    fn var_id_classify_only_applies_to_flags() {
+        let s = settings(100, 200, 250);
+        assert_eq!(VarId::Variable(3).classify(&s), None);
+        assert_eq!(VarId::Flag(150).classify(&s), Some(FlagKind::Item(50)));
+    }
+
+    #[test]
+    fn flags_a_dead_write() {
+        let mut script = Script::new();
+        let entry = script.alloc_block();
+        // `set_flag 1` is never read by anything reachable afterward, so it's dead.
+        script.define_block(entry, code(vec![Command::SetFlag(1, Expr::Imm(1)), Command::Return], None));
+
+        let liveness = Liveness::compute(&script, [entry]);
+        let code_block = match script.block(entry) {
+            Block::Code(c) => c,
+            Block::Data(_) => unreachable!(),
+        };
+        assert_eq!(liveness.dead_writes(entry, code_block), vec![VarId::Flag(1)]);
+    }
+
+    #[test]
+    fn a_write_read_by_a_later_block_is_not_dead() {
+        let mut script = Script::new();
+        let entry = script.alloc_block();
+        let exit = script.alloc_block();
+        script.define_block(entry, code(vec![Command::SetFlag(1, Expr::Imm(1))], Some(exit)));
+        script.define_block(exit, code(vec![Command::If(Expr::Flag(1), Ip::Block(entry))], None));
+
+        let liveness = Liveness::compute(&script, [entry]);
+        let code_block = match script.block(entry) {
+            Block::Code(c) => c,
+            Block::Data(_) => unreachable!(),
+        };
+        assert!(liveness.dead_writes(entry, code_block).is_empty());
+        assert!(liveness.live_in(exit).unwrap().contains(VarId::Flag(1)));
+    }
+
+    #[test]
+    fn fixpoint_converges_over_a_loop_with_multiple_predecessors() {
+        // entry -> header -> body -> header (back edge), header -> exit
+        let mut script = Script::new();
+        let entry = script.alloc_block();
+        let header = script.alloc_block();
+        let body = script.alloc_block();
+        let exit = script.alloc_block();
+
+        script.define_block(entry, code(vec![Command::SetFlag(7, Expr::Imm(1))], Some(header)));
+        script.define_block(
+            header,
+            code(vec![Command::If(Expr::Flag(7), Ip::Block(body))], Some(exit)),
+        );
+        script.define_block(body, code(vec![Command::Goto(Ip::Block(header))], None));
+        script.define_block(exit, code(vec![Command::Return], None));
+
+        let liveness = Liveness::compute(&script, [entry]);
+        // The flag set in `entry` must stay live across the loop back-edge into `header`.
+        assert!(liveness.live_in(header).unwrap().contains(VarId::Flag(7)));
+        assert!(liveness.live_out(body).unwrap().contains(VarId::Flag(7)));
+    }
+}
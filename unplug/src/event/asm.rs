@@ -0,0 +1,415 @@
+//! Textual assembly format for event scripts.
+//!
+//! This lets modders edit script logic as readable text — labels and per-event directives
+//! instead of raw `Ip` offsets — then reassemble it into a [`Script`]. [`disassemble`] produces
+//! the text from a decoded script, and [`assemble`] parses it back. Instruction-level syntax is
+//! handled by the [`ReadAsm`]/[`WriteAsm`] impls on `Command` in this module, which spell out each
+//! mnemonic here rather than taking them from `command`/`opcodes`.
+
+use super::block::{Block, BlockId, CodeBlock, Ip};
+use super::command::Command;
+use super::expr::{Expr, SetExpr};
+use super::script::Script;
+use std::collections::{HashMap, VecDeque};
+use std::error::Error as StdError;
+use std::fmt;
+
+/// An error encountered while assembling script text.
+#[derive(Debug)]
+pub enum AsmError {
+    /// A `.directive` wasn't recognized.
+    UnknownDirective(String),
+    /// An instruction referenced a label that was never defined.
+    UnknownLabel(String),
+    /// An instruction line couldn't be parsed by [`Command::read_asm`].
+    Instruction(String),
+}
+
+impl fmt::Display for AsmError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnknownDirective(d) => write!(f, "unknown directive: .{}", d),
+            Self::UnknownLabel(l) => write!(f, "undefined label: {}", l),
+            Self::Instruction(msg) => write!(f, "{}", msg),
+        }
+    }
+}
+
+impl StdError for AsmError {}
+
+/// Parses an instruction from its mnemonic/operand text, resolving label operands to `BlockId`s
+/// through `labels`. Implemented by `Command`.
+pub trait ReadAsm: Sized {
+    fn read_asm(line: &str, labels: &HashMap<String, BlockId>) -> Result<Self, AsmError>;
+}
+
+/// Renders an instruction back to mnemonic/operand text, naming jump targets through `names`.
+/// Implemented by `Command`.
+pub trait WriteAsm {
+    fn write_asm(&self, names: &HashMap<BlockId, String>) -> String;
+}
+
+/// Maps each `.on_*`/`.object N` directive name to the `BlockId` of its event root.
+pub type EventMap = HashMap<String, BlockId>;
+
+/// The result of [`assemble`]: the reconstructed script plus a map from each `.on_*`/`.object N`
+/// directive to the `BlockId` of the block that followed it.
+pub struct Assembled {
+    pub script: Script,
+    pub events: EventMap,
+}
+
+/// Assembles `text` into a [`Script`], resolving labels to a fresh block graph.
+pub fn assemble(text: &str) -> Result<Assembled, AsmError> {
+    enum Line<'a> {
+        Directive(&'a str),
+        Label(&'a str),
+        Instruction(&'a str),
+    }
+
+    let lines: Vec<Line<'_>> = text
+        .lines()
+        .map(|line| line.split(';').next().unwrap_or("").trim())
+        .filter(|line| !line.is_empty())
+        .map(|line| {
+            if let Some(directive) = line.strip_prefix('.') {
+                Line::Directive(directive)
+            } else if let Some(label) = line.strip_suffix(':') {
+                Line::Label(label.trim())
+            } else {
+                Line::Instruction(line)
+            }
+        })
+        .collect();
+
+    // First pass: assign every label a block in file order, and remember which label follows
+    // each directive.
+    let mut script = Script::new();
+    let mut labels = HashMap::new();
+    let mut order = Vec::new();
+    let mut events = EventMap::new();
+    let mut pending_directives: Vec<String> = Vec::new();
+    for line in &lines {
+        match line {
+            Line::Directive(d) => {
+                if !is_known_directive(d) {
+                    return Err(AsmError::UnknownDirective((*d).to_owned()));
+                }
+                pending_directives.push((*d).to_owned());
+            }
+            Line::Label(name) => {
+                let id = script.alloc_block();
+                labels.insert((*name).to_owned(), id);
+                order.push(*name);
+                for directive in pending_directives.drain(..) {
+                    events.insert(directive, id);
+                }
+            }
+            Line::Instruction(_) => {}
+        }
+    }
+
+    // Second pass: parse each block's instructions now that every label resolves to a `BlockId`,
+    // and fall through to the block that follows it in the text unless the block is last.
+    let mut current: Option<&str> = None;
+    let mut bodies: HashMap<&str, Vec<&str>> = HashMap::new();
+    for line in &lines {
+        match line {
+            Line::Directive(_) => {}
+            Line::Label(name) => current = Some(name),
+            Line::Instruction(text) => {
+                if let Some(name) = current {
+                    bodies.entry(name).or_default().push(text);
+                }
+            }
+        }
+    }
+    for (i, name) in order.iter().enumerate() {
+        let mut commands = Vec::new();
+        for line in bodies.get(name).into_iter().flatten() {
+            commands.push(Command::read_asm(line, &labels)?);
+        }
+        let next = order.get(i + 1).map(|next| labels[*next]);
+        script.define_block(labels[name], Block::Code(CodeBlock { commands, next }));
+    }
+
+    Ok(Assembled { script, events })
+}
+
+const DIRECTIVES: &[&str] =
+    &["on_prologue", "on_startup", "on_dead", "on_pose", "on_time_cycle", "on_time_up"];
+
+/// Whether `directive` is a recognized `.on_*` event directive or an `.object N` directive.
+fn is_known_directive(directive: &str) -> bool {
+    DIRECTIVES.contains(&directive)
+        || directive
+            .strip_prefix("object ")
+            .map_or(false, |n| n.trim().parse::<u32>().is_ok())
+}
+
+/// Disassembles the subroutines in `events` (each a directive name and its entry `BlockId`) into
+/// readable text, reusing the same label/jump layout an assembled [`Script`] round-trips through.
+pub fn disassemble(script: &Script, events: &[(&str, BlockId)]) -> String {
+    // Assign every reachable block a stable label in BFS discovery order across all events, so
+    // blocks shared between subroutines are only named once.
+    let mut names: HashMap<BlockId, String> = HashMap::new();
+    let mut queue: VecDeque<BlockId> = events.iter().map(|(_, id)| *id).collect();
+    while let Some(id) = queue.pop_front() {
+        if names.contains_key(&id) {
+            continue;
+        }
+        let label = format!("L{}", names.len());
+        names.insert(id, label);
+        if let Block::Code(code) = script.block(id) {
+            for (_, target) in code.successors() {
+                if let Some(next) = target.resolved() {
+                    queue.push_back(next);
+                }
+            }
+        }
+    }
+
+    let mut out = String::new();
+    // Shared across every directive (not reset per-event, mirroring `CfgBuilder::build_dot`), so a
+    // block reachable from more than one event/object root — a common shared teardown/pickup
+    // subroutine — is only emitted once instead of once per reaching event. Every directive whose
+    // root is a block is printed right before that block's label, so `assemble` maps each of them
+    // back to the same block.
+    let mut visited = std::collections::HashSet::new();
+    for &(_, root) in events {
+        if visited.contains(&root) {
+            continue;
+        }
+        let mut queue = VecDeque::from([root]);
+        while let Some(id) = queue.pop_front() {
+            if !visited.insert(id) {
+                continue;
+            }
+            for &(directive, _) in events.iter().filter(|&&(_, r)| r == id) {
+                out.push_str(&format!(".{}\n", directive));
+            }
+            out.push_str(&format!("{}:\n", names[&id]));
+            if let Block::Code(code) = script.block(id) {
+                for cmd in &code.commands {
+                    out.push_str("    ");
+                    out.push_str(&cmd.write_asm(&names));
+                    out.push('\n');
+                }
+                for (_, target) in code.successors() {
+                    if let Some(next) = target.resolved() {
+                        queue.push_back(next);
+                    }
+                }
+            }
+        }
+        out.push('\n');
+    }
+    out
+}
+
+fn resolve_label(name: &str, labels: &HashMap<String, BlockId>) -> Result<Ip, AsmError> {
+    labels.get(name).map(|&id| Ip::Block(id)).ok_or_else(|| AsmError::UnknownLabel(name.to_owned()))
+}
+
+fn label_name(ip: Ip, names: &HashMap<BlockId, String>) -> String {
+    ip.resolved().and_then(|id| names.get(&id)).cloned().unwrap_or_else(|| "?".to_owned())
+}
+
+/// Parses the minimal expression syntax the assembler supports: `$N` for a variable slot, `f(N)`
+/// for a flag, and a bare integer for an immediate.
+fn parse_expr(text: &str) -> Result<Expr, AsmError> {
+    let text = text.trim();
+    if let Some(rest) = text.strip_prefix('$') {
+        let index = rest
+            .parse::<i16>()
+            .map_err(|e| AsmError::Instruction(format!("bad variable {:?}: {}", text, e)))?;
+        return Ok(Expr::Variable(index));
+    }
+    if let Some(rest) = text.strip_prefix("f(").and_then(|s| s.strip_suffix(')')) {
+        let index =
+            rest.parse::<i16>().map_err(|e| AsmError::Instruction(format!("bad flag {:?}: {}", text, e)))?;
+        return Ok(Expr::Flag(index));
+    }
+    text.parse::<i32>().map(Expr::Imm).map_err(|e| AsmError::Instruction(format!("bad expr {:?}: {}", text, e)))
+}
+
+/// Renders an expression in the syntax [`parse_expr`] accepts.
+fn render_expr(expr: &Expr) -> String {
+    match expr {
+        Expr::Variable(index) => format!("${}", index),
+        Expr::Flag(index) => format!("f({})", index),
+        Expr::Imm(value) => value.to_string(),
+    }
+}
+
+fn operands(text: &str) -> Vec<&str> {
+    if text.is_empty() {
+        Vec::new()
+    } else {
+        text.split(',').map(str::trim).collect()
+    }
+}
+
+impl ReadAsm for Command {
+    fn read_asm(line: &str, labels: &HashMap<String, BlockId>) -> Result<Self, AsmError> {
+        let mut parts = line.splitn(2, char::is_whitespace);
+        let mnemonic = parts.next().unwrap_or("").trim();
+        let ops = operands(parts.next().unwrap_or("").trim());
+        let arg = |i: usize| -> Result<&str, AsmError> {
+            ops.get(i).copied().ok_or_else(|| {
+                AsmError::Instruction(format!("{}: expected at least {} operand(s)", mnemonic, i + 1))
+            })
+        };
+        match mnemonic {
+            "nop" => Ok(Command::Nop),
+            "return" => Ok(Command::Return),
+            "goto" => Ok(Command::Goto(resolve_label(arg(0)?, labels)?)),
+            "set" => Ok(Command::Set(SetExpr {
+                target: parse_expr(arg(0)?)?,
+                value: parse_expr(arg(1)?)?,
+            })),
+            "if" => Ok(Command::If(parse_expr(arg(0)?)?, resolve_label(arg(1)?, labels)?)),
+            "set_flag" => {
+                let flag = arg(0)?
+                    .parse::<i16>()
+                    .map_err(|e| AsmError::Instruction(format!("bad flag {:?}: {}", arg(0)?, e)))?;
+                Ok(Command::SetFlag(flag, parse_expr(arg(1)?)?))
+            }
+            _ => Err(AsmError::Instruction(format!("unknown mnemonic: {}", mnemonic))),
+        }
+    }
+}
+
+/// Renders an instruction as mnemonic/operand text with unresolved jump targets (used by
+/// [`super::analysis::CfgBuilder`] for node labels, where targets are shown as raw `Ip`s rather
+/// than the named labels [`WriteAsm::write_asm`] produces).
+impl fmt::Display for Command {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Command::Nop => write!(f, "nop"),
+            Command::Return => write!(f, "return"),
+            Command::Goto(target) => write!(f, "goto {:?}", target),
+            Command::Set(SetExpr { target, value }) => {
+                write!(f, "set {}, {}", render_expr(target), render_expr(value))
+            }
+            Command::If(cond, target) => write!(f, "if {}, {:?}", render_expr(cond), target),
+            Command::SetFlag(flag, value) => write!(f, "set_flag {}, {}", flag, render_expr(value)),
+        }
+    }
+}
+
+impl WriteAsm for Command {
+    fn write_asm(&self, names: &HashMap<BlockId, String>) -> String {
+        match self {
+            Command::Nop => "nop".to_owned(),
+            Command::Return => "return".to_owned(),
+            Command::Goto(target) => format!("goto {}", label_name(*target, names)),
+            Command::Set(SetExpr { target, value }) => {
+                format!("set {}, {}", render_expr(target), render_expr(value))
+            }
+            Command::If(cond, target) => format!("if {}, {}", render_expr(cond), label_name(*target, names)),
+            Command::SetFlag(flag, value) => format!("set_flag {}, {}", flag, render_expr(value)),
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_straight_line_code() {
+        let text = "\
+.on_startup
+L0:
+    set $1, 5
+    set_flag 12, $1
+    return
+";
+        let assembled = assemble(text).unwrap();
+        let root = assembled.events["on_startup"];
+        let out = disassemble(&assembled.script, &[("on_startup", root)]);
+        let reassembled = assemble(&out).unwrap();
+        assert_eq!(out, disassemble(&reassembled.script, &[("on_startup", reassembled.events["on_startup"])]));
+    }
+
+    #[test]
+    fn round_trips_a_conditional_branch() {
+        let text = "\
+.on_dead
+L0:
+    if $1, L2
+L1:
+    set_flag 3, 1
+    goto L2
+L2:
+    return
+";
+        let assembled = assemble(text).unwrap();
+        let root = assembled.events["on_dead"];
+
+        // The entry block should fall through to `L1` and branch to `L2`.
+        let successors: Vec<_> =
+            match assembled.script.block(root) {
+                Block::Code(code) => code.successors().filter_map(|(_, ip)| ip.resolved()).collect(),
+                Block::Data(_) => panic!("expected a code block"),
+            };
+        assert_eq!(successors.len(), 2);
+
+        let out = disassemble(&assembled.script, &[("on_dead", root)]);
+        let reassembled = assemble(&out).unwrap();
+        assert_eq!(
+            disassemble(&reassembled.script, &[("on_dead", reassembled.events["on_dead"])]),
+            out
+        );
+    }
+
+    #[test]
+    fn disassemble_emits_a_block_shared_between_events_only_once() {
+        let text = "\
+.on_startup
+L0:
+    goto L2
+.on_dead
+L1:
+    goto L2
+L2:
+    return
+";
+        let assembled = assemble(text).unwrap();
+        let on_startup = assembled.events["on_startup"];
+        let on_dead = assembled.events["on_dead"];
+        let out = disassemble(
+            &assembled.script,
+            &[("on_startup", on_startup), ("on_dead", on_dead)],
+        );
+
+        // The shared `return` block must be labeled and printed exactly once, not once per
+        // event that reaches it.
+        let body_lines: Vec<&str> = out.lines().filter(|line| line.trim() == "return").collect();
+        assert_eq!(body_lines.len(), 1, "shared block printed more than once:\n{}", out);
+
+        // Reassembling must reproduce the same two distinct entry blocks with no orphaned or
+        // merged bodies.
+        let reassembled = assemble(&out).unwrap();
+        assert_ne!(reassembled.events["on_startup"], reassembled.events["on_dead"]);
+        let reassembled_out = disassemble(
+            &reassembled.script,
+            &[("on_startup", reassembled.events["on_startup"]), ("on_dead", reassembled.events["on_dead"])],
+        );
+        assert_eq!(out, reassembled_out);
+    }
+
+    #[test]
+    fn rejects_an_undefined_label() {
+        let text = ".on_startup\nL0:\n    goto L9\n";
+        assert!(matches!(assemble(text), Err(AsmError::UnknownLabel(_))));
+    }
+
+    #[test]
+    fn rejects_an_unknown_directive() {
+        let text = ".on_blorp\nL0:\n    return\n";
+        assert!(matches!(assemble(text), Err(AsmError::UnknownDirective(_))));
+    }
+}
@@ -1,9 +1,12 @@
 use super::{Actor, Error, Object, Result};
 use crate::common::{NonNoneList, ReadFrom, ReadOptionFrom, WriteOptionTo, WriteTo};
+use crate::event::analysis::CfgBuilder;
+use crate::event::asm::{self, AsmError};
 use crate::event::block::BlockId;
 use crate::event::script::{Script, ScriptReader, ScriptWriter};
 use crate::globals::Libs;
 use byteorder::{ByteOrder, ReadBytesExt, WriteBytesExt, BE, LE};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use std::io::{Read, Seek, SeekFrom, Write};
 use std::num::NonZeroU32;
 
@@ -79,7 +82,7 @@ impl<W: Write> WriteTo<W> for Header {
 
 const SETTINGS_SIZE: u32 = 20;
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Settings {
     pub unk_00: i32,
     pub unk_04: u8,
@@ -155,7 +158,7 @@ impl<W: Write> WriteTo<W> for EventTable {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Unk28 {
     unk_00: i32,
     unk_04: i32,
@@ -230,7 +233,7 @@ impl<W: Write> WriteOptionTo<W> for Unk28 {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Unk2C {
     unk_00: i32,
     unk_04: i32,
@@ -287,6 +290,15 @@ impl<W: Write> WriteOptionTo<W> for Unk2C {
     }
 }
 
+/// A decoded stage.
+///
+/// `Stage` has hand-written `Serialize`/`Deserialize` impls (see [`StageRepr`] below) so it can be
+/// dumped to a human-editable text format (RON/JSON/YAML), hand-edited, and reloaded before being
+/// re-emitted through [`WriteTo`]. Events are stored here as [`BlockId`]s, which are only
+/// meaningful as indices into this `Stage`'s own `script` arena; serializing them directly would
+/// dump raw arena indices that don't survive a hand-edit reordering the text. Instead, `Serialize`
+/// renders every event and object script through [`Stage::disassemble`] and `Deserialize`
+/// rebuilds `script` and every `BlockId` from that text through [`asm::assemble`].
 #[derive(Clone)]
 pub struct Stage {
     pub objects: Vec<Object>,
@@ -316,7 +328,133 @@ pub struct Stage {
     pub unk_30: Vec<Unk28>,
 }
 
+/// On-disk shape of [`Stage`]'s human-editable serialization: event/object scripts as assembly
+/// text instead of raw [`BlockId`]s, so a hand-edited file can reorder or add subroutines.
+#[derive(Serialize, Deserialize)]
+struct StageRepr {
+    objects: Vec<Object>,
+    actors: Vec<Actor>,
+    settings: Settings,
+    unk_28: Vec<Unk28>,
+    unk_2c: Vec<Unk2C>,
+    unk_30: Vec<Unk28>,
+    events: String,
+}
+
+impl Serialize for Stage {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        // The objects' own `script` fields are raw `BlockId`s too; `events` already carries each
+        // object's subroutine under an `.object N` directive, so null them out here rather than
+        // serialize the same information twice in two different (and divergent) forms.
+        let mut objects = self.objects.clone();
+        for obj in &mut objects {
+            obj.script = None;
+        }
+        StageRepr {
+            objects,
+            actors: self.actors.clone(),
+            settings: self.settings.clone(),
+            unk_28: self.unk_28.clone(),
+            unk_2c: self.unk_2c.clone(),
+            unk_30: self.unk_30.clone(),
+            events: self.disassemble(),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Stage {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        let repr = StageRepr::deserialize(deserializer)?;
+        let asm::Assembled { script, events } =
+            asm::assemble(&repr.events).map_err(serde::de::Error::custom)?;
+        let mut objects = repr.objects;
+        for (i, obj) in objects.iter_mut().enumerate() {
+            obj.script = events.get(&format!("object {}", i)).copied();
+        }
+        Ok(Stage {
+            objects,
+            actors: repr.actors,
+            script,
+            on_prologue: events.get("on_prologue").copied(),
+            on_startup: events.get("on_startup").copied(),
+            on_dead: events.get("on_dead").copied(),
+            on_pose: events.get("on_pose").copied(),
+            on_time_cycle: events.get("on_time_cycle").copied(),
+            on_time_up: events.get("on_time_up").copied(),
+            settings: repr.settings,
+            unk_28: repr.unk_28,
+            unk_2c: repr.unk_2c,
+            unk_30: repr.unk_30,
+        })
+    }
+}
+
 impl Stage {
+    /// Renders a Graphviz DOT `digraph` of the control-flow graph reachable from every event and
+    /// object script in this stage, for visually inspecting the decoded subroutines.
+    pub fn control_flow_graph(&self) -> String {
+        let roots = self
+            .on_prologue
+            .into_iter()
+            .chain(self.on_startup)
+            .chain(self.on_dead)
+            .chain(self.on_pose)
+            .chain(self.on_time_cycle)
+            .chain(self.on_time_up)
+            .chain(self.objects.iter().filter_map(|obj| obj.script));
+        CfgBuilder::new(&self.script).build_dot(roots)
+    }
+
+    /// Disassembles every event and object script in this stage into readable assembly text,
+    /// with `.on_startup`/`.on_dead`/.../`.object N` directives marking each subroutine.
+    pub fn disassemble(&self) -> String {
+        let mut events: Vec<(&str, BlockId)> = Vec::new();
+        if let Some(id) = self.on_prologue {
+            events.push(("on_prologue", id));
+        }
+        if let Some(id) = self.on_startup {
+            events.push(("on_startup", id));
+        }
+        if let Some(id) = self.on_dead {
+            events.push(("on_dead", id));
+        }
+        if let Some(id) = self.on_pose {
+            events.push(("on_pose", id));
+        }
+        if let Some(id) = self.on_time_cycle {
+            events.push(("on_time_cycle", id));
+        }
+        if let Some(id) = self.on_time_up {
+            events.push(("on_time_up", id));
+        }
+        let object_directives: Vec<(String, BlockId)> = self
+            .objects
+            .iter()
+            .enumerate()
+            .filter_map(|(i, obj)| obj.script.map(|id| (format!("object {}", i), id)))
+            .collect();
+        events.extend(object_directives.iter().map(|(name, id)| (name.as_str(), *id)));
+        asm::disassemble(&self.script, &events)
+    }
+
+    /// Reassembles this stage's events and object scripts from text produced by
+    /// [`Stage::disassemble`], replacing `self.script` and every event/object `BlockId`.
+    pub fn assemble(&mut self, text: &str) -> std::result::Result<(), AsmError> {
+        let asm::Assembled { script, events } = asm::assemble(text)?;
+        self.on_prologue = events.get("on_prologue").copied();
+        self.on_startup = events.get("on_startup").copied();
+        self.on_dead = events.get("on_dead").copied();
+        self.on_pose = events.get("on_pose").copied();
+        self.on_time_cycle = events.get("on_time_cycle").copied();
+        self.on_time_up = events.get("on_time_up").copied();
+        for (i, obj) in self.objects.iter_mut().enumerate() {
+            obj.script = events.get(&format!("object {}", i)).copied();
+        }
+        self.script = script;
+        Ok(())
+    }
+
     pub fn read_from<R: Read + Seek>(reader: &mut R, libs: &Libs) -> Result<Self> {
         let header = Header::read_from(reader)?;
 
@@ -453,3 +591,68 @@ impl<W: Write + Seek> WriteTo<W> for Stage {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event::block::{Block, BlockId, CodeBlock, Ip};
+    use crate::event::command::Command;
+
+    fn code(commands: Vec<Command>, next: Option<BlockId>) -> Block {
+        Block::Code(CodeBlock { commands, next })
+    }
+
+    fn settings() -> Settings {
+        Settings {
+            unk_00: 0,
+            unk_04: 0,
+            unk_05: 0,
+            unk_06: 0,
+            unk_08: 0,
+            unk_09: 0,
+            item_flags_base: 0,
+            coin_flags_base: 0,
+            dust_flags_base: 0,
+            unk_10: 0,
+            unk_12: 0,
+        }
+    }
+
+    /// A `.on_startup` event and object 0's script sharing a single root block: the scenario that
+    /// silently corrupted the reassembled text before `asm::disassemble` deduplicated visited
+    /// blocks across events (see the chunk0-4 fix).
+    #[test]
+    fn round_trips_through_text_with_a_block_shared_by_an_event_and_an_object() {
+        let mut script = Script::new();
+        let shared = script.alloc_block();
+        script.define_block(shared, code(vec![Command::Return], None));
+
+        let stage = Stage {
+            objects: vec![Object { script: Some(shared), ..Default::default() }],
+            actors: vec![],
+            script,
+            on_prologue: None,
+            on_startup: Some(shared),
+            on_dead: None,
+            on_pose: None,
+            on_time_cycle: None,
+            on_time_up: None,
+            settings: settings(),
+            unk_28: vec![],
+            unk_2c: vec![],
+            unk_30: vec![],
+        };
+
+        let json = serde_json::to_string(&stage).unwrap();
+        let reloaded: Stage = serde_json::from_str(&json).unwrap();
+
+        // The event and the object must still point at the very same reconstructed block...
+        assert_eq!(reloaded.on_startup, reloaded.objects[0].script);
+        // ...whose body must not have been duplicated or merged with anything else.
+        let text = reloaded.disassemble();
+        assert_eq!(text.matches("return").count(), 1, "shared block corrupted:\n{}", text);
+
+        // The round trip is idempotent: re-serializing the reloaded stage reproduces the same text.
+        assert_eq!(serde_json::to_string(&reloaded).unwrap(), json);
+    }
+}